@@ -0,0 +1,119 @@
+//! Fee-estimation strategies for the priority/max fee paid by built transactions.
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::Provider;
+use clap::ValueEnum;
+use eyre::Result;
+
+/// CLI-selectable strategy kind, mapped to a [`FeeStrategy`] once the
+/// percentile argument is known.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeStrategyArg {
+    /// Fixed heuristic: 10% of base fee (min 2 gwei tip) plus a 25% buffer.
+    Fixed,
+    /// Poll `eth_gasPrice` and derive a tip from the spread over base fee.
+    PercentileOracle,
+    /// Pull the last ~20 blocks of `eth_feeHistory` and use a reward percentile.
+    EthFeeHistory,
+}
+
+/// A concrete fee-estimation strategy, ready to query a provider.
+pub enum FeeStrategy {
+    Fixed,
+    PercentileOracle,
+    EthFeeHistory { percentile: f64 },
+}
+
+/// `(max_priority_fee_per_gas, max_fee_per_gas)` from the fixed 10%/25% heuristic.
+fn fixed_fees(base_fee: u128) -> (u128, u128) {
+    let priority_fee = (base_fee / 10).max(2_000_000_000); // 10% of base fee or 2 gwei minimum
+    let max_fee = base_fee + priority_fee + (base_fee / 4); // base + tip + 25% buffer
+    (priority_fee, max_fee)
+}
+
+/// Median of the per-block percentile rewards returned by `eth_feeHistory`,
+/// falling back to a 2 gwei floor if the history came back empty.
+fn median_reward(rewards: &mut [u128]) -> u128 {
+    if rewards.is_empty() {
+        return 2_000_000_000;
+    }
+    rewards.sort_unstable();
+    rewards[rewards.len() / 2]
+}
+
+impl FeeStrategy {
+    pub fn new(kind: FeeStrategyArg, percentile: f64) -> Self {
+        match kind {
+            FeeStrategyArg::Fixed => FeeStrategy::Fixed,
+            FeeStrategyArg::PercentileOracle => FeeStrategy::PercentileOracle,
+            FeeStrategyArg::EthFeeHistory => FeeStrategy::EthFeeHistory { percentile },
+        }
+    }
+
+    /// Returns `(max_priority_fee_per_gas, max_fee_per_gas)`.
+    pub async fn estimate<P: Provider>(&self, provider: &P, base_fee: u128) -> Result<(u128, u128)> {
+        match self {
+            FeeStrategy::Fixed => Ok(fixed_fees(base_fee)),
+            FeeStrategy::PercentileOracle => {
+                let gas_price = provider.get_gas_price().await?;
+                let priority_fee = gas_price.saturating_sub(base_fee).max(1_000_000_000);
+                let max_fee = base_fee + priority_fee;
+                Ok((priority_fee, max_fee))
+            }
+            FeeStrategy::EthFeeHistory { percentile } => {
+                let history = provider
+                    .get_fee_history(20, BlockNumberOrTag::Pending, &[*percentile])
+                    .await?;
+
+                let mut rewards: Vec<u128> = history
+                    .reward
+                    .as_ref()
+                    .map(|rewards| rewards.iter().filter_map(|block| block.first().copied()).collect())
+                    .unwrap_or_default();
+
+                let priority_fee = median_reward(&mut rewards);
+                let pending_base_fee = *history.base_fee_per_gas.last().unwrap_or(&base_fee);
+                let max_fee = pending_base_fee * 2 + priority_fee;
+
+                Ok((priority_fee, max_fee))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_fees_applies_minimum_tip_and_buffer() {
+        let (priority_fee, max_fee) = fixed_fees(1_000_000_000);
+        assert_eq!(priority_fee, 2_000_000_000); // 10% of base is below the 2 gwei floor
+        assert_eq!(max_fee, 1_000_000_000 + 2_000_000_000 + 250_000_000);
+    }
+
+    #[test]
+    fn fixed_fees_uses_ten_percent_of_base_when_above_floor() {
+        let (priority_fee, max_fee) = fixed_fees(100_000_000_000);
+        assert_eq!(priority_fee, 10_000_000_000);
+        assert_eq!(max_fee, 100_000_000_000 + 10_000_000_000 + 25_000_000_000);
+    }
+
+    #[test]
+    fn median_reward_picks_the_middle_value() {
+        let mut rewards = vec![5, 1, 3, 2, 4];
+        assert_eq!(median_reward(&mut rewards), 3);
+    }
+
+    #[test]
+    fn median_reward_is_not_swayed_by_a_single_outlier() {
+        let mut rewards = vec![1, 1, 1, 1, 1_000_000_000];
+        assert_eq!(median_reward(&mut rewards), 1);
+    }
+
+    #[test]
+    fn median_reward_falls_back_to_floor_when_history_is_empty() {
+        let mut rewards: Vec<u128> = vec![];
+        assert_eq!(median_reward(&mut rewards), 2_000_000_000);
+    }
+}
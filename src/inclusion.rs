@@ -0,0 +1,70 @@
+//! Block-driven inclusion polling, as an alternative to a wall-clock watch.
+//!
+//! Bundles target a specific `maxBlockNumber` and may legitimately land
+//! several blocks later (or never, if the builder drops them), so we poll
+//! new block headers for the expected transaction hashes instead of racing a
+//! fixed timeout.
+
+use std::time::Duration;
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::primitives::B256;
+use alloy::providers::Provider;
+use eyre::Result;
+
+/// How often to re-check for a new head while polling.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InclusionStatus {
+    /// The transaction was found in the given block.
+    Included { block_number: u64 },
+    /// The polling window closed without the transaction appearing.
+    NotIncluded,
+}
+
+/// Poll new blocks for each of `tx_hashes`, up to `wait_blocks` blocks past
+/// the current head, returning the resolved status of every hash.
+pub async fn poll_for_inclusion<P: Provider>(
+    provider: &P,
+    tx_hashes: &[B256],
+    wait_blocks: u64,
+) -> Result<Vec<(B256, InclusionStatus)>> {
+    let start_block = provider.get_block_number().await?;
+    let deadline_block = start_block + wait_blocks;
+
+    let mut pending: Vec<B256> = tx_hashes.to_vec();
+    let mut statuses = vec![InclusionStatus::NotIncluded; tx_hashes.len()];
+
+    let mut next_block = start_block;
+    while next_block <= deadline_block && !pending.is_empty() {
+        let head = provider.get_block_number().await?;
+        if next_block > head {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        if let Some(block) = provider
+            .get_block_by_number(BlockNumberOrTag::Number(next_block))
+            .await?
+        {
+            let included_in_block = block.transactions.hashes().collect::<Vec<_>>();
+
+            pending.retain(|tx_hash| {
+                if included_in_block.contains(tx_hash) {
+                    let idx = tx_hashes.iter().position(|h| h == tx_hash).unwrap();
+                    statuses[idx] = InclusionStatus::Included {
+                        block_number: next_block,
+                    };
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        next_block += 1;
+    }
+
+    Ok(tx_hashes.iter().copied().zip(statuses).collect())
+}
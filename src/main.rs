@@ -1,18 +1,21 @@
-use std::time::Duration;
+mod fees;
+mod inclusion;
 
 use alloy::{
     eips::{BlockNumberOrTag, eip2718::Encodable2718},
     hex,
-    network::{EthereumWallet, TransactionBuilder},
-    primitives::{B256, Bytes, address},
-    providers::{PendingTransactionBuilder, Provider, ProviderBuilder},
-    rpc::types::TransactionRequest,
+    network::{EthereumWallet, NetworkTransactionBuilder, TransactionBuilder},
+    primitives::{Address, B256, Bytes, address},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::{AccessList, AccessListItem, TransactionRequest},
     signers::local::PrivateKeySigner,
 };
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use eyre::Result;
 use serde::{Deserialize, Serialize};
 
+use fees::{FeeStrategy, FeeStrategyArg};
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -35,6 +38,92 @@ struct Args {
     /// Whether to send the transaction as a bundle
     #[arg(long)]
     bundle: bool,
+
+    /// Transaction hash(es) in the bundle that are allowed to revert without
+    /// invalidating the whole bundle. Repeatable.
+    #[arg(long = "allow-revert", value_name = "TX_HASH")]
+    allow_revert: Vec<B256>,
+
+    /// Transaction hash(es) in the bundle that are allowed to be dropped
+    /// without invalidating the whole bundle. Repeatable.
+    #[arg(long = "allow-drop", value_name = "TX_HASH")]
+    allow_drop: Vec<B256>,
+
+    /// Simulate the bundle with `eth_callBundle` and print a per-transaction
+    /// revert report instead of submitting it.
+    #[arg(long)]
+    simulate: bool,
+
+    /// Number of chained transactions to build into the bundle. Nonces are
+    /// assigned sequentially starting from the account's current nonce,
+    /// mirroring a nonce-manager middleware (no re-querying the node).
+    #[arg(long, default_value_t = 1)]
+    count: u64,
+
+    /// Make only the transaction at this 0-based index revert, leaving the
+    /// rest non-reverting. Overrides `--reverts` when set.
+    #[arg(long)]
+    reverts_at: Option<u64>,
+
+    /// Fee estimation strategy used to compute the priority/max fee.
+    #[arg(long, value_enum, default_value_t = FeeStrategyArg::Fixed)]
+    fee_strategy: FeeStrategyArg,
+
+    /// Reward percentile (0-100) used by the `eth-fee-history` strategy.
+    #[arg(long, default_value_t = 50.0)]
+    fee_percentile: f64,
+
+    /// Print the receipt assertion results as JSON instead of a log line per tx.
+    #[arg(long)]
+    json: bool,
+
+    /// Number of blocks to poll for inclusion before giving up on a tx/bundle.
+    #[arg(long, default_value_t = 5)]
+    wait_blocks: u64,
+
+    /// Transaction envelope type to build.
+    #[arg(long = "tx-type", value_enum, default_value_t = TxType::Eip1559)]
+    tx_type: TxType,
+
+    /// Access list entries for `--tx-type eip2930`, formatted `addr:slot`,
+    /// comma-separated.
+    #[arg(long = "access-list", value_delimiter = ',')]
+    access_list: Vec<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum TxType {
+    /// EIP-155 transaction with a single `gasPrice`, no access list.
+    Legacy,
+    /// EIP-2930 transaction: `gasPrice` plus an access list.
+    Eip2930,
+    /// EIP-1559 transaction with `maxPriorityFeePerGas`/`maxFeePerGas`.
+    Eip1559,
+}
+
+fn parse_access_list(entries: &[String]) -> Result<AccessList> {
+    use std::collections::BTreeMap;
+
+    let mut grouped: BTreeMap<Address, Vec<B256>> = BTreeMap::new();
+    for entry in entries {
+        let (addr_str, slot_str) = entry
+            .split_once(':')
+            .ok_or_else(|| eyre::eyre!("invalid --access-list entry `{entry}`, expected `addr:slot`"))?;
+
+        let address: Address = addr_str.parse()?;
+        let slot: B256 = slot_str.parse()?;
+        grouped.entry(address).or_default().push(slot);
+    }
+
+    Ok(AccessList(
+        grouped
+            .into_iter()
+            .map(|(address, storage_keys)| AccessListItem {
+                address,
+                storage_keys,
+            })
+            .collect(),
+    ))
 }
 
 fn parse_rpc_url(input: &str) -> Result<String, String> {
@@ -54,6 +143,20 @@ pub struct Bundle {
 
     #[serde(rename = "maxBlockNumber")]
     pub block_number_max: Option<u64>,
+
+    #[serde(
+        rename = "revertingTxHashes",
+        skip_serializing_if = "Vec::is_empty",
+        default
+    )]
+    pub reverting_tx_hashes: Vec<B256>,
+
+    #[serde(
+        rename = "droppingTxHashes",
+        skip_serializing_if = "Vec::is_empty",
+        default
+    )]
+    pub dropping_tx_hashes: Vec<B256>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -62,6 +165,44 @@ pub struct BundleResult {
     pub bundle_hash: B256,
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct CallBundleParams {
+    pub txs: Vec<Bytes>,
+
+    #[serde(rename = "blockNumber")]
+    pub block_number: String,
+
+    #[serde(rename = "stateBlockNumber")]
+    pub state_block_number: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SimulatedTx {
+    #[serde(rename = "txHash")]
+    pub tx_hash: B256,
+
+    #[serde(rename = "gasUsed")]
+    pub gas_used: u64,
+
+    pub value: Option<Bytes>,
+    pub error: Option<String>,
+    pub revert: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleSimulation {
+    #[serde(rename = "bundleHash")]
+    pub bundle_hash: B256,
+
+    #[serde(rename = "coinbaseDiff")]
+    pub coinbase_diff: Option<alloy::primitives::U256>,
+
+    #[serde(rename = "totalGasUsed")]
+    pub total_gas_used: Option<u64>,
+
+    pub results: Vec<SimulatedTx>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
@@ -88,41 +229,125 @@ async fn main() -> Result<()> {
         .map(|block| block.header.base_fee_per_gas.expect("base fee"))
         .unwrap() as u128;
 
-    // Add these lines:
-    let priority_fee = (base_fee / 10).max(2_000_000_000); // 10% of base fee or 2 gwei minimum
-    let max_fee = base_fee + priority_fee + (base_fee / 4); // base + tip + 25% buffer
+    let fee_strategy = FeeStrategy::new(args.fee_strategy, args.fee_percentile);
+    let (priority_fee, max_fee) = fee_strategy.estimate(&provider, base_fee).await?;
 
     println!(
-        "Sending transaction that reverts: {:?}, with bundle {:?}",
-        args.reverts, args.bundle
+        "Sending {} {:?} transaction(s) that revert at index {:?}, with bundle {:?}",
+        args.count, args.tx_type, args.reverts_at, args.bundle
     );
 
+    if args.count == 0 {
+        eyre::bail!("--count must be at least 1");
+    }
+
+    if args.count > 1 && !args.bundle {
+        eyre::bail!("--count > 1 requires --bundle to chain the transactions together");
+    }
+
+    if let Some(revert_index) = args.reverts_at {
+        if revert_index >= args.count {
+            eyre::bail!(
+                "--reverts-at {revert_index} is out of range for --count {}",
+                args.count
+            );
+        }
+    }
+
     let balance = provider.get_balance(pk_addr).await?;
     if balance.is_zero() {
         eyre::bail!("Insufficient balance for the transaction. Please fund the account.");
     }
 
-    let mut tx = TransactionRequest::default()
-        .with_gas_limit(300000)
-        .with_chain_id(chain_id)
-        .with_nonce(nonce)
-        .with_max_priority_fee_per_gas(priority_fee)
-        .with_max_fee_per_gas(max_fee);
-
-    if args.reverts {
-        tx.set_deploy_code(Bytes::from(hex!("60006000fd")));
+    let access_list = if args.tx_type == TxType::Eip2930 {
+        parse_access_list(&args.access_list)?
     } else {
-        tx.set_to(address!("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"))
+        if !args.access_list.is_empty() {
+            println!(
+                "Warning: --access-list is ignored for --tx-type {:?}",
+                args.tx_type
+            );
+        }
+        AccessList(Vec::new())
     };
 
-    let tx_envelope = tx.build(&wallet).await?;
-    let tx_encoded = tx_envelope.encoded_2718();
+    let mut txs_encoded: Vec<Bytes> = Vec::with_capacity(args.count as usize);
+    let mut tx_hashes: Vec<B256> = Vec::with_capacity(args.count as usize);
+    let mut reverts_flags: Vec<bool> = Vec::with_capacity(args.count as usize);
+    for i in 0..args.count {
+        let mut tx = TransactionRequest::default()
+            .with_gas_limit(300000)
+            .with_chain_id(chain_id)
+            .with_nonce(nonce + i);
+
+        tx = match args.tx_type {
+            TxType::Eip1559 => tx
+                .with_max_priority_fee_per_gas(priority_fee)
+                .with_max_fee_per_gas(max_fee),
+            TxType::Eip2930 => tx
+                .with_gas_price(max_fee)
+                .with_access_list(access_list.clone()),
+            TxType::Legacy => tx.with_gas_price(max_fee),
+        };
+
+        let should_revert = match args.reverts_at {
+            Some(revert_index) => revert_index == i,
+            None => args.reverts && i == 0,
+        };
+
+        if should_revert {
+            tx.set_deploy_code(Bytes::from(hex!("60006000fd")));
+        } else {
+            tx.set_to(address!("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"))
+        };
+
+        let tx_envelope = tx.build(&wallet).await?;
+        tx_hashes.push(*tx_envelope.tx_hash());
+        reverts_flags.push(should_revert);
+        txs_encoded.push(tx_envelope.encoded_2718().into());
+    }
+
+    if args.simulate {
+        let target_block = provider.get_block_number().await? + 1;
+
+        let call_bundle = CallBundleParams {
+            txs: txs_encoded.clone(),
+            block_number: format!("0x{target_block:x}"),
+            state_block_number: "latest".to_string(),
+        };
+
+        let simulation: BundleSimulation = provider
+            .client()
+            .request("eth_callBundle", (call_bundle,))
+            .await?;
+
+        println!(
+            "Simulation result for bundle {} (total gas used: {:?}, coinbase diff: {:?}):",
+            simulation.bundle_hash, simulation.total_gas_used, simulation.coinbase_diff
+        );
+        println!("{:<5} {:<66} {:<10} {:<10} error", "#", "txHash", "gasUsed", "reverted");
+        for (i, sim_tx) in simulation.results.iter().enumerate() {
+            let reverted = sim_tx.revert.is_some() || sim_tx.error.is_some();
+            println!(
+                "{:<5} {:<66} {:<10} {:<10} {}",
+                i,
+                sim_tx.tx_hash,
+                sim_tx.gas_used,
+                reverted,
+                sim_tx.error.as_deref().unwrap_or("-")
+            );
+        }
+
+        return Ok(());
+    }
 
-    // Send the transaction and wait for inclusion.
-    let pending_tx = if args.bundle {
+    // Send the transaction(s).
+    if args.bundle {
         let bundle = Bundle {
-            transactions: vec![tx_encoded.into()],
+            transactions: txs_encoded,
             block_number_max: None,
+            reverting_tx_hashes: args.allow_revert.clone(),
+            dropping_tx_hashes: args.allow_drop.clone(),
         };
 
         let result: BundleResult = provider
@@ -130,25 +355,127 @@ async fn main() -> Result<()> {
             .request("eth_sendBundle", (bundle,))
             .await?;
 
-        PendingTransactionBuilder::new(provider.root().clone(), result.bundle_hash)
+        println!("Submitted bundle: {}", result.bundle_hash);
     } else {
-        let pending = provider.send_raw_transaction(&tx_encoded).await?;
-        pending
-    };
+        let pending = provider.send_raw_transaction(&txs_encoded[0]).await?;
+        println!("Submitted transaction: {}", pending.tx_hash());
+    }
 
-    let pending_tx = pending_tx.with_timeout(Some(Duration::from_secs(20)));
+    // Bundles can legitimately land several blocks after submission, so poll
+    // block-by-block for inclusion rather than racing a wall-clock timeout.
+    println!(
+        "Polling up to {} block(s) for inclusion...",
+        args.wait_blocks
+    );
+
+    let inclusion_statuses = inclusion::poll_for_inclusion(&provider, &tx_hashes, args.wait_blocks).await?;
+    for (tx_hash, status) in &inclusion_statuses {
+        match status {
+            inclusion::InclusionStatus::Included { block_number } => {
+                println!("{tx_hash}: included at block {block_number}");
+            }
+            inclusion::InclusionStatus::NotIncluded => {
+                println!("{tx_hash}: not included, polling window closed");
+            }
+        }
+    }
+
+    // Assert that each transaction's on-chain status matches what we intended.
+    // A tx that was expected to revert/drop and was simply never included is
+    // the revert-protection happy path, not a failure - only fetch (and
+    // compare against) a receipt when the tx actually landed on chain.
+    let mut any_mismatch = false;
+    let mut results = Vec::with_capacity(tx_hashes.len());
 
-    println!("Sent transaction: {}", pending_tx.tx_hash());
-    println!("Waiting for transaction to be mined...");
+    for (i, (tx_hash, inclusion_status)) in tx_hashes.iter().zip(
+        inclusion_statuses
+            .iter()
+            .map(|(_, status)| status),
+    ).enumerate() {
+        let expected_success = !reverts_flags[i];
 
-    match pending_tx.watch().await {
-        Ok(tx_hash) => {
-            println!("Transaction mined: {}", tx_hash);
+        let (matches_expectation, actual_success, included_block, gas_used) = match inclusion_status
+        {
+            inclusion::InclusionStatus::Included { block_number } => {
+                let receipt = provider.get_transaction_receipt(*tx_hash).await?;
+                match receipt {
+                    Some(r) => {
+                        let success = r.status();
+                        (success == expected_success, Some(success), Some(*block_number), Some(r.gas_used))
+                    }
+                    None => (false, None, Some(*block_number), None),
+                }
+            }
+            inclusion::InclusionStatus::NotIncluded => {
+                // Only a bundle can legitimately drop a tx without including
+                // it (revert protection excluding it pre-execution). A
+                // standalone tx has no such path: not landing is always a
+                // failure, even if we expected it to revert on-chain - the
+                // assertion contract requires an actual receipt with status 0.
+                (args.bundle && !expected_success, None, None, None)
+            }
+        };
+
+        if !matches_expectation {
+            any_mismatch = true;
         }
-        Err(e) => {
-            println!("Error watching transaction: {}", e);
+
+        if !args.json {
+            println!(
+                "tx[{i}] {tx_hash}: expected_success={expected_success} actual_success={actual_success:?} included_block={included_block:?} gas_used={gas_used:?} matches={matches_expectation}"
+            );
         }
+
+        results.push(serde_json::json!({
+            "tx_hash": tx_hash.to_string(),
+            "expected": expected_success,
+            "actual": actual_success,
+            "included_block": included_block,
+            "gas_used": gas_used,
+            "matches": matches_expectation,
+        }));
+    }
+
+    if args.json {
+        println!("{}", serde_json::Value::Array(results));
+    }
+
+    if any_mismatch {
+        eyre::bail!("one or more transactions did not match their expected revert status");
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_access_list_groups_slots_by_address() {
+        let addr = "0x0000000000000000000000000000000000000001";
+        let slot_a = "0x0000000000000000000000000000000000000000000000000000000000000001";
+        let slot_b = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+        let access_list = parse_access_list(&[
+            format!("{addr}:{slot_a}"),
+            format!("{addr}:{slot_b}"),
+        ])
+        .unwrap();
+
+        assert_eq!(access_list.0.len(), 1);
+        assert_eq!(access_list.0[0].storage_keys.len(), 2);
+    }
+
+    #[test]
+    fn parse_access_list_empty_input_yields_empty_list() {
+        let access_list = parse_access_list(&[]).unwrap();
+        assert!(access_list.0.is_empty());
+    }
+
+    #[test]
+    fn parse_access_list_rejects_malformed_entry() {
+        let err = parse_access_list(&["not-a-valid-entry".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("addr:slot"));
+    }
+}